@@ -1,3 +1,77 @@
 //! Chain namespace.
+// NOTE: This should be kept in sync with go/common/namespace.go.
+use byteorder::{BigEndian, ByteOrder};
+
+/// Size of the identifier component of a namespace.
+pub const NAMESPACE_ID_SIZE: usize = Namespace::len() - 8;
+
+/// Namespace flag indicating a test runtime.
+pub const NAMESPACE_FLAG_TEST: u64 = 1 << 63;
+/// Namespace flag indicating a key manager runtime.
+pub const NAMESPACE_FLAG_KEY_MANAGER: u64 = 1 << 62;
+
+const NAMESPACE_FLAGS_RESERVED: u64 = !(NAMESPACE_FLAG_TEST | NAMESPACE_FLAG_KEY_MANAGER);
 
 impl_bytes!(Namespace, 32, "Chain namespace.");
+
+impl Namespace {
+    /// Returns true iff the namespace is for a test runtime.
+    pub fn is_test(&self) -> bool {
+        self.flags() & NAMESPACE_FLAG_TEST != 0
+    }
+
+    /// Returns true iff the namespace is for a key manager runtime.
+    pub fn is_key_manager(&self) -> bool {
+        self.flags() & NAMESPACE_FLAG_KEY_MANAGER != 0
+    }
+
+    /// Returns true iff none of the reserved flag bits are set.
+    pub fn is_valid(&self) -> bool {
+        self.flags() & NAMESPACE_FLAGS_RESERVED == 0
+    }
+
+    fn flags(&self) -> u64 {
+        BigEndian::read_u64(&self.0[0..8])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_namespace_flags() {
+        let ns =
+            Namespace::from("8000000000000000000000000000000000000000000000000000000000000000");
+        assert!(ns.is_valid());
+        assert!(ns.is_test());
+        assert!(!ns.is_key_manager());
+
+        let ns =
+            Namespace::from("c000000000000000000000000000000000000000000000000000000000000001");
+        assert!(ns.is_valid());
+        assert!(ns.is_test());
+        assert!(ns.is_key_manager());
+
+        let ns = Namespace::default();
+        assert!(ns.is_valid());
+        assert!(!ns.is_test());
+        assert!(!ns.is_key_manager());
+
+        let ns =
+            Namespace::from("0000000000000001000000000000000000000000000000000000000000000000");
+        assert!(!ns.is_valid());
+    }
+
+    #[test]
+    fn test_namespace_hex() {
+        let s = "c000000000000000ffffffffffffffffffffffffffffffffffffffffffffffff";
+        let ns: Namespace = s.parse().unwrap();
+        assert_eq!(format!("{:x}", ns), s);
+        assert_eq!(format!("{:?}", ns), s);
+        assert_eq!(s.parse::<Namespace>().unwrap(), ns);
+
+        assert!("c000".parse::<Namespace>().is_err());
+        assert!("zz".parse::<Namespace>().is_err());
+    }
+}
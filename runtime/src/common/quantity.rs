@@ -8,6 +8,14 @@ use std::{
 
 use num_bigint::BigUint;
 use num_traits::{CheckedDiv, CheckedSub, ToPrimitive, Zero};
+use thiserror::Error;
+
+/// Quantity-related error.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum QuantityError {
+    #[error("insufficient balance")]
+    InsufficientBalance,
+}
 
 /// An arbitrary precision unsigned integer.
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -22,6 +30,18 @@ impl Quantity {
         self.0.checked_sub(&other.0).map(Quantity)
     }
 
+    /// Subtracts the given amount in place. If the amount is larger than the current value,
+    /// `QuantityError::InsufficientBalance` is returned and the value is left unchanged.
+    pub fn try_sub(&mut self, other: &Quantity) -> Result<(), QuantityError> {
+        match self.checked_sub(other) {
+            Some(result) => {
+                *self = result;
+                Ok(())
+            }
+            None => Err(QuantityError::InsufficientBalance),
+        }
+    }
+
     /// Divides two numbers, checking for underflow, overflow and division by zero. If any of that
     /// happens, `None` is returned.
     #[inline]
@@ -221,9 +241,10 @@ impl cbor::Decode for Quantity {
 
 #[cfg(test)]
 mod test {
+    use num_traits::Zero;
     use rustc_hex::ToHex;
 
-    use crate::common::quantity::Quantity;
+    use crate::common::quantity::{Quantity, QuantityError};
 
     #[test]
     fn test_serialization() {
@@ -302,4 +323,28 @@ mod test {
         );
         assert_eq!(a.checked_div(&Quantity::from(0u32)), None);
     }
+
+    #[test]
+    fn test_try_sub() {
+        let a = Quantity::from(1000u32);
+        assert_eq!(
+            a.checked_sub(&Quantity::from(1000u32)),
+            Some(Quantity::zero())
+        );
+
+        let mut a = Quantity::from(1000u32);
+        assert_eq!(a.try_sub(&Quantity::from(400u32)), Ok(()));
+        assert_eq!(a, Quantity::from(600u32));
+        assert_eq!(
+            a.try_sub(&Quantity::from(601u32)),
+            Err(QuantityError::InsufficientBalance)
+        );
+        assert_eq!(
+            a,
+            Quantity::from(600u32),
+            "failed sub should not modify value"
+        );
+        assert_eq!(a.try_sub(&Quantity::from(600u32)), Ok(()));
+        assert!(a.is_zero());
+    }
 }
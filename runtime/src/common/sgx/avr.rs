@@ -40,6 +40,8 @@ enum AVRError {
     MissingQuoteBody,
     #[error("AVR did not contain nonce")]
     MissingNonce,
+    #[error("malformed AVR advisory IDs")]
+    MalformedAdvisoryIDs,
     #[error("failed to parse quote")]
     MalformedQuote,
     #[error("unable to find exactly 2 certificates")]
@@ -156,6 +158,9 @@ impl QuoteBody {
     }
 }
 
+/// Number of seconds after its timestamp an AVR is considered valid.
+pub const AVR_VALIDITY_SECS: i64 = 60 * 60 * 24;
+
 /// Attestation verification report.
 #[derive(Debug, Clone, cbor::Encode, cbor::Decode)]
 pub struct AVR {
@@ -171,7 +176,11 @@ pub struct AuthenticatedAVR {
     // TODO: add other av report/quote body/report fields we want to give the consumer
     pub identity: EnclaveIdentity,
     pub timestamp: i64,
+    /// POSIX time at which the AVR stops being fresh.
+    pub expiry: i64,
     pub nonce: String,
+    pub quote_status: String,
+    pub advisory_ids: Vec<String>,
 }
 
 /// Parsed AVR body.
@@ -213,12 +222,32 @@ impl ParsedAVR {
         parse_avr_timestamp(&timestamp)
     }
 
+    fn expiry(&self) -> Result<i64> {
+        Ok(self.timestamp()? + AVR_VALIDITY_SECS)
+    }
+
     pub(crate) fn nonce(&self) -> Result<String> {
         match self.body["nonce"].as_str() {
             Some(nonce) => Ok(nonce.to_string()),
             None => Err(AVRError::MissingNonce.into()),
         }
     }
+
+    fn advisory_ids(&self) -> Result<Vec<String>> {
+        let advisory_ids = match self.body.get("advisoryIDs") {
+            Some(advisory_ids) => advisory_ids,
+            // Advisory IDs are only present for some quote statuses.
+            None => return Ok(vec![]),
+        };
+        advisory_ids
+            .as_array()
+            .and_then(|ids| {
+                ids.iter()
+                    .map(|id| id.as_str().map(|id| id.to_string()))
+                    .collect()
+            })
+            .ok_or_else(|| AVRError::MalformedAdvisoryIDs.into())
+    }
 }
 
 /// Verify attestation report.
@@ -272,6 +301,10 @@ pub fn verify(avr: &AVR) -> Result<AuthenticatedAVR> {
         }
     };
 
+    // Reject reports with a malformed advisory ID list even if the quote status is acceptable,
+    // so that advisories are never silently dropped.
+    let advisory_ids = avr_body.advisory_ids()?;
+
     let quote_body = avr_body.isv_enclave_quote_body()?;
     let quote_body = match base64::decode(&quote_body) {
         Ok(quote_body) => quote_body,
@@ -306,7 +339,10 @@ pub fn verify(avr: &AVR) -> Result<AuthenticatedAVR> {
             mr_signer: MrSigner::from(quote_body.report_body.mrsigner.to_vec()),
         },
         timestamp,
+        expiry: avr_body.expiry()?,
         nonce: nonce.to_string(),
+        quote_status,
+        advisory_ids,
     })
 }
 
@@ -479,7 +515,7 @@ fn check_certificate_rsa_signature(cert: &X509Certificate, public_key: &RSAPubli
 /// Return true iff the (POXIX) timestamp is considered "fresh" for the purposes
 /// of a cached AVR, given the current time.
 pub(crate) fn timestamp_is_fresh(now: i64, timestamp: i64) -> bool {
-    (now - timestamp).abs() < 60 * 60 * 24
+    (now - timestamp).abs() < AVR_VALIDITY_SECS
 }
 
 /// Enclave identity.
@@ -562,4 +598,45 @@ mod tests {
         let timestamp = parse_avr_timestamp("2018-03-30T22:02:26.123456").unwrap();
         assert_eq!(timestamp, SIG_AT as i64);
     }
+
+    #[test]
+    fn test_parsed_avr() {
+        const MSG: &[u8] = include_bytes!("../../../testdata/avr_body_group_out_of_date.json");
+
+        let avr = AVR {
+            body: MSG.to_vec(),
+            signature: vec![],
+            certificate_chain: vec![],
+        };
+        let parsed = ParsedAVR::new(&avr).unwrap();
+        assert_eq!(parsed.timestamp().unwrap(), 1522447345);
+        assert_eq!(parsed.expiry().unwrap(), 1522447345 + AVR_VALIDITY_SECS);
+        assert_eq!(
+            parsed.isv_enclave_quote_status().unwrap(),
+            "GROUP_OUT_OF_DATE"
+        );
+        assert!(parsed.advisory_ids().unwrap().is_empty());
+
+        let avr = AVR {
+            body: br#"{"nonce":"abcd","advisoryIDs":["INTEL-SA-00334","INTEL-SA-00219"]}"#.to_vec(),
+            signature: vec![],
+            certificate_chain: vec![],
+        };
+        let parsed = ParsedAVR::new(&avr).unwrap();
+        assert_eq!(parsed.nonce().unwrap(), "abcd");
+        assert_eq!(
+            parsed.advisory_ids().unwrap(),
+            vec!["INTEL-SA-00334".to_string(), "INTEL-SA-00219".to_string()]
+        );
+        assert!(parsed.timestamp().is_err());
+        assert!(parsed.expiry().is_err());
+
+        let avr = AVR {
+            body: br#"{"advisoryIDs":[1]}"#.to_vec(),
+            signature: vec![],
+            certificate_chain: vec![],
+        };
+        let parsed = ParsedAVR::new(&avr).unwrap();
+        assert!(parsed.advisory_ids().is_err());
+    }
 }
@@ -1,8 +1,11 @@
 //! Protocol and runtime versioning.
 // NOTE: This should be kept in sync with go/common/version/version.go.
+use std::fmt;
 
 /// A protocol or runtime version.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, cbor::Encode, cbor::Decode)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, cbor::Encode, cbor::Decode,
+)]
 pub struct Version {
     #[cbor(optional)]
     #[cbor(default)]
@@ -47,6 +50,12 @@ impl Version {
     }
 }
 
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
 // Returns the version as a platform-dependent u64.
 impl Into<u64> for Version {
     fn into(self) -> u64 {
@@ -107,4 +116,19 @@ mod test {
         let vi: u64 = v.into();
         assert_eq!(v, Version::from(vi));
     }
+
+    #[test]
+    fn test_version_ordering() {
+        let v = Version::new(1, 2, 3);
+        assert_eq!(format!("{}", v), "1.2.3");
+
+        assert!(v < Version::new(1, 2, 4));
+        assert!(v < Version::new(1, 3, 0));
+        assert!(v < Version::new(2, 0, 0));
+        assert!(v > Version::new(1, 1, 10));
+        assert!(v > Version::new(0, 10, 10));
+
+        assert!(v.is_compatible_with(&Version::new(1, 5, 0)));
+        assert!(!v.is_compatible_with(&Version::new(2, 2, 3)));
+    }
 }
@@ -6,6 +6,8 @@
 //!
 use std::collections::BTreeMap;
 
+use thiserror::Error;
+
 use crate::{
     common::{
         crypto::{
@@ -16,10 +18,25 @@ use crate::{
         quantity,
         version::Version,
     },
-    consensus::{scheduler, staking},
+    consensus::{address::Address, scheduler, staking},
     storage::mkvs::WriteLog,
 };
 
+/// Registry error.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RegistryError {
+    #[error("registry: incorrect tx signer")]
+    IncorrectTxSigner,
+    #[error("registry: forbidden by policy")]
+    Forbidden,
+    #[error("registry: candidate pool too small (size: {size} minimum: {min})")]
+    PoolTooSmall { size: usize, min: u16 },
+    #[error("registry: too many nodes for entity {entity:?}")]
+    TooManyNodes { entity: PublicKey },
+    #[error("registry: entity {entity:?} not in validator set")]
+    NotValidator { entity: PublicKey },
+}
+
 /// Runtime kind.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, cbor::Encode, cbor::Decode)]
 #[repr(u32)]
@@ -105,6 +122,52 @@ pub struct SchedulingConstraints {
     pub min_pool_size: Option<MinPoolSizeConstraint>,
 }
 
+impl SchedulingConstraints {
+    /// Checks a candidate committee against the constraints.
+    ///
+    /// The `members` slice holds the controlling entity of each selected node, `pool_size` is the
+    /// size of the candidate pool that the committee was selected from and `is_validator` returns
+    /// whether the given entity has a node that is part of the validator set.
+    pub fn check(
+        &self,
+        pool_size: usize,
+        members: &[PublicKey],
+        is_validator: &dyn Fn(&PublicKey) -> bool,
+    ) -> Result<(), RegistryError> {
+        if let Some(ref mps) = self.min_pool_size {
+            if pool_size < mps.limit as usize {
+                return Err(RegistryError::PoolTooSmall {
+                    size: pool_size,
+                    min: mps.limit,
+                });
+            }
+        }
+
+        if let Some(ref mn) = self.max_nodes {
+            let mut counts: BTreeMap<&PublicKey, usize> = BTreeMap::new();
+            for entity in members {
+                let count = counts.entry(entity).or_default();
+                *count += 1;
+                if *count > mn.limit as usize {
+                    return Err(RegistryError::TooManyNodes {
+                        entity: entity.clone(),
+                    });
+                }
+            }
+        }
+
+        if self.validator_set.is_some() {
+            if let Some(entity) = members.iter().find(|entity| !is_validator(entity)) {
+                return Err(RegistryError::NotValidator {
+                    entity: entity.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// A constraint which specifies that the entity must have a node that is part of the validator set.
 /// No other options can currently be specified.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, cbor::Encode, cbor::Decode)]
@@ -189,6 +252,48 @@ impl Default for RuntimeAdmissionPolicy {
     }
 }
 
+impl RuntimeAdmissionPolicy {
+    /// Checks whether a new node of the given entity with the given roles may register for the
+    /// runtime.
+    ///
+    /// The `current_counts` map holds the number of other (non-expired) nodes of the same entity
+    /// that are already registered for the runtime, per role.
+    pub fn is_admitted(
+        &self,
+        entity_id: &PublicKey,
+        roles: &[RolesMask],
+        current_counts: &BTreeMap<RolesMask, u16>,
+    ) -> Result<(), RegistryError> {
+        let policy = match self {
+            RuntimeAdmissionPolicy::AnyNode {} => return Ok(()),
+            RuntimeAdmissionPolicy::EntityWhitelist(policy) => policy,
+        };
+
+        let wcfg = policy
+            .entities
+            .as_ref()
+            .and_then(|entities| entities.get(entity_id))
+            .ok_or(RegistryError::Forbidden)?;
+        let max_nodes = match wcfg.max_nodes {
+            Some(ref max_nodes) if !max_nodes.is_empty() => max_nodes,
+            // Number of nodes is unlimited.
+            _ => return Ok(()),
+        };
+
+        // Map is present and non-empty, check per-role restrictions on the maximum number of
+        // nodes per entity. Any missing roles imply zero nodes.
+        for role in roles {
+            let limit = max_nodes.get(role).copied().unwrap_or_default();
+            let current = current_counts.get(role).copied().unwrap_or_default();
+            if current >= limit {
+                return Err(RegistryError::Forbidden);
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Runtime governance model.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, cbor::Encode, cbor::Decode)]
 #[repr(u8)]
@@ -283,6 +388,37 @@ pub struct Runtime {
     pub governance_model: RuntimeGovernanceModel,
 }
 
+impl Runtime {
+    /// Staking address of the account controlling the runtime, if any.
+    ///
+    /// Runtimes using the consensus layer governance model are not controlled by any account.
+    pub fn staking_address(&self) -> Option<Address> {
+        match self.governance_model {
+            RuntimeGovernanceModel::GovernanceEntity => Some(Address::from_pk(&self.entity_id)),
+            RuntimeGovernanceModel::GovernanceRuntime => Some(Address::from_runtime_id(&self.id)),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the given caller is allowed to update the runtime under its governance
+    /// model.
+    ///
+    /// In case the update changes the governance model, this must be called on the existing
+    /// runtime descriptor and not the updated one.
+    pub fn authorize_update(&self, caller: &Address) -> Result<(), RegistryError> {
+        // Runtimes with consensus layer governance can only be updated via governance.
+        let expected = self.staking_address().ok_or(RegistryError::Forbidden)?;
+        if &expected == caller {
+            return Ok(());
+        }
+
+        match self.governance_model {
+            RuntimeGovernanceModel::GovernanceEntity => Err(RegistryError::IncorrectTxSigner),
+            _ => Err(RegistryError::Forbidden),
+        }
+    }
+}
+
 fn staking_params_are_empty(p: &RuntimeStakingParameters) -> bool {
     return Option::is_none(&p.thresholds);
 }
@@ -306,3 +442,160 @@ pub struct RuntimeGenesis {
     /// Runtime round in the genesis.
     pub round: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduling_constraints() {
+        let entity_a =
+            PublicKey::from("4ea5328f943ef6f66daaed74cb0e99c3b1c45f76307b425003dbc7cb3638ed35");
+        let entity_b =
+            PublicKey::from("47aadd91516ac548decdb436fde957992610facc09ba2f850da0fe1b2be96119");
+        let members = vec![entity_a.clone(), entity_a.clone(), entity_b.clone()];
+        let all_validators = |_: &PublicKey| true;
+        let only_a = |entity: &PublicKey| entity == &entity_a;
+
+        // No constraints.
+        let sc = SchedulingConstraints::default();
+        assert_eq!(sc.check(0, &members, &only_a), Ok(()));
+
+        // Minimum pool size.
+        let sc = SchedulingConstraints {
+            min_pool_size: Some(MinPoolSizeConstraint { limit: 5 }),
+            ..Default::default()
+        };
+        assert_eq!(sc.check(5, &members, &all_validators), Ok(()));
+        assert_eq!(
+            sc.check(4, &members, &all_validators),
+            Err(RegistryError::PoolTooSmall { size: 4, min: 5 })
+        );
+
+        // Maximum nodes per entity.
+        let sc = SchedulingConstraints {
+            max_nodes: Some(MaxNodesConstraint { limit: 2 }),
+            ..Default::default()
+        };
+        assert_eq!(sc.check(3, &members, &all_validators), Ok(()));
+        let sc = SchedulingConstraints {
+            max_nodes: Some(MaxNodesConstraint { limit: 1 }),
+            ..Default::default()
+        };
+        assert_eq!(
+            sc.check(3, &members, &all_validators),
+            Err(RegistryError::TooManyNodes {
+                entity: entity_a.clone()
+            })
+        );
+
+        // Validator set membership.
+        let sc = SchedulingConstraints {
+            validator_set: Some(ValidatorSetConstraint {}),
+            ..Default::default()
+        };
+        assert_eq!(sc.check(3, &members, &all_validators), Ok(()));
+        assert_eq!(
+            sc.check(3, &members, &only_a),
+            Err(RegistryError::NotValidator { entity: entity_b })
+        );
+    }
+
+    #[test]
+    fn test_authorize_update() {
+        let entity_id =
+            PublicKey::from("4ea5328f943ef6f66daaed74cb0e99c3b1c45f76307b425003dbc7cb3638ed35");
+        let other_id =
+            PublicKey::from("47aadd91516ac548decdb436fde957992610facc09ba2f850da0fe1b2be96119");
+        let runtime_id =
+            Namespace::from("8000000000000000000000000000000000000000000000000000000000000000");
+
+        let mut rt = Runtime {
+            id: runtime_id.clone(),
+            entity_id: entity_id.clone(),
+            governance_model: RuntimeGovernanceModel::GovernanceEntity,
+            ..Default::default()
+        };
+        assert_eq!(rt.authorize_update(&Address::from_pk(&entity_id)), Ok(()));
+        assert_eq!(
+            rt.authorize_update(&Address::from_pk(&other_id)),
+            Err(RegistryError::IncorrectTxSigner)
+        );
+        assert_eq!(
+            rt.authorize_update(&Address::from_runtime_id(&runtime_id)),
+            Err(RegistryError::IncorrectTxSigner)
+        );
+
+        rt.governance_model = RuntimeGovernanceModel::GovernanceRuntime;
+        assert_eq!(
+            rt.authorize_update(&Address::from_runtime_id(&runtime_id)),
+            Ok(())
+        );
+        assert_eq!(
+            rt.authorize_update(&Address::from_pk(&entity_id)),
+            Err(RegistryError::Forbidden)
+        );
+
+        rt.governance_model = RuntimeGovernanceModel::GovernanceConsensus;
+        assert_eq!(
+            rt.authorize_update(&Address::from_pk(&entity_id)),
+            Err(RegistryError::Forbidden)
+        );
+    }
+
+    #[test]
+    fn test_admission_policy() {
+        let entity_id =
+            PublicKey::from("4ea5328f943ef6f66daaed74cb0e99c3b1c45f76307b425003dbc7cb3638ed35");
+        let other_id =
+            PublicKey::from("47aadd91516ac548decdb436fde957992610facc09ba2f850da0fe1b2be96119");
+        let no_counts = BTreeMap::new();
+
+        let policy = RuntimeAdmissionPolicy::AnyNode {};
+        assert_eq!(
+            policy.is_admitted(&other_id, &[RolesMask::RoleComputeWorker], &no_counts),
+            Ok(())
+        );
+
+        let mut max_nodes = BTreeMap::new();
+        max_nodes.insert(RolesMask::RoleComputeWorker, 2);
+        let mut entities = BTreeMap::new();
+        entities.insert(
+            entity_id.clone(),
+            EntityWhitelistConfig {
+                max_nodes: Some(max_nodes),
+            },
+        );
+        let policy =
+            RuntimeAdmissionPolicy::EntityWhitelist(EntityWhitelistRuntimeAdmissionPolicy {
+                entities: Some(entities),
+            });
+
+        // Whitelisted entity under the limit.
+        let mut counts = BTreeMap::new();
+        counts.insert(RolesMask::RoleComputeWorker, 1);
+        assert_eq!(
+            policy.is_admitted(&entity_id, &[RolesMask::RoleComputeWorker], &counts),
+            Ok(())
+        );
+
+        // Entity not in the whitelist.
+        assert_eq!(
+            policy.is_admitted(&other_id, &[RolesMask::RoleComputeWorker], &no_counts),
+            Err(RegistryError::Forbidden)
+        );
+
+        // Whitelist limit reached.
+        counts.insert(RolesMask::RoleComputeWorker, 2);
+        assert_eq!(
+            policy.is_admitted(&entity_id, &[RolesMask::RoleComputeWorker], &counts),
+            Err(RegistryError::Forbidden)
+        );
+
+        // Roles missing from a non-empty map are not allowed.
+        assert_eq!(
+            policy.is_admitted(&entity_id, &[RolesMask::RoleStorageWorker], &no_counts),
+            Err(RegistryError::Forbidden)
+        );
+    }
+}
@@ -168,6 +168,16 @@ impl Header {
     pub fn encoded_hash(&self) -> Hash {
         Hash::digest_bytes(&cbor::to_vec(self.clone()))
     }
+
+    /// Returns true iff the header is the parent of a child header.
+    pub fn is_parent_of(&self, child: &Header) -> bool {
+        self.encoded_hash() == child.previous_hash
+    }
+
+    /// Returns true iff the header is the child of a parent header.
+    pub fn is_child_of(&self, parent: &Header) -> bool {
+        parent.is_parent_of(self)
+    }
 }
 
 /// Compute results header signature context.
@@ -237,6 +247,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_header_parent_child() {
+        let parent = Header {
+            version: 42,
+            namespace: Namespace::from(Hash::empty_hash().as_ref()),
+            round: 1000,
+            timestamp: 1560257841,
+            header_type: HeaderType::Normal,
+            previous_hash: Header::default().encoded_hash(),
+            io_root: Hash::empty_hash(),
+            state_root: Hash::empty_hash(),
+            messages_hash: Hash::empty_hash(),
+            ..Default::default()
+        };
+        let child = Header {
+            round: parent.round + 1,
+            previous_hash: parent.encoded_hash(),
+            ..parent.clone()
+        };
+
+        assert!(parent.is_parent_of(&child));
+        assert!(child.is_child_of(&parent));
+        assert!(!child.is_parent_of(&parent));
+        assert!(!parent.is_child_of(&child));
+        assert!(!parent.is_parent_of(&parent));
+    }
+
     #[test]
     fn test_consistent_hash_compute_results_header() {
         // NOTE: These hashes MUST be synced with go/roothash/api/commitment/executor_test.go.
@@ -12,7 +12,7 @@ use std::{
 use anyhow::{anyhow, Result as AnyResult};
 use crossbeam::channel;
 use io_context::Context;
-use slog::{debug, error, info, warn, Logger};
+use slog::{debug, error, info, o, warn, Logger};
 
 use crate::{
     common::{
@@ -33,7 +33,7 @@ use crate::{
         types::{Message as RpcMessage, Request as RpcRequest},
         Context as RpcContext,
     },
-    protocol::{Protocol, ProtocolUntrustedLocalStorage},
+    protocol::{Protocol, ProtocolError, ProtocolUntrustedLocalStorage},
     rak::RAK,
     storage::{
         mkvs::{
@@ -51,8 +51,8 @@ use crate::{
     types::{Body, ComputedBatch, Error, HostStorageEndpoint},
 };
 
-/// Maximum amount of requests that can be in the dispatcher queue.
-const BACKLOG_SIZE: usize = 1000;
+/// Default maximum amount of requests that can be in the dispatcher queue.
+pub const BACKLOG_SIZE: usize = 1000;
 
 /// Interface for dispatcher initializers.
 pub trait Initializer: Send + Sync {
@@ -90,6 +90,25 @@ where
 
 type QueueItem = (Context, u64, Body);
 
+/// Name of the dispatched request used in log records.
+fn request_method(body: &Body) -> &'static str {
+    match body {
+        Body::RuntimeRPCCallRequest { .. } => "RuntimeRPCCallRequest",
+        Body::RuntimeLocalRPCCallRequest { .. } => "RuntimeLocalRPCCallRequest",
+        Body::RuntimeExecuteTxBatchRequest { .. } => "RuntimeExecuteTxBatchRequest",
+        Body::RuntimeCheckTxBatchRequest { .. } => "RuntimeCheckTxBatchRequest",
+        Body::RuntimeKeyManagerPolicyUpdateRequest { .. } => "RuntimeKeyManagerPolicyUpdateRequest",
+        Body::RuntimeQueryRequest { .. } => "RuntimeQueryRequest",
+        Body::RuntimeAbortRequest { .. } => "RuntimeAbortRequest",
+        _ => "unknown",
+    }
+}
+
+/// Create a child logger for a dispatched request.
+fn request_logger(logger: &Logger, id: u64, body: &Body) -> Logger {
+    logger.new(o!("request_id" => id, "method" => request_method(body)))
+}
+
 /// A guard that will abort the process if dropped while panicking.
 ///
 /// This is to ensure that the runtime will terminate in case there is
@@ -119,8 +138,14 @@ pub struct Dispatcher {
 
 impl Dispatcher {
     /// Create a new runtime call dispatcher.
-    pub fn new(initializer: Box<dyn Initializer>, rak: Arc<RAK>) -> Arc<Self> {
-        let (tx, rx) = channel::bounded(BACKLOG_SIZE);
+    ///
+    /// At most `max_queue_depth` requests can be waiting in the dispatcher queue at any time.
+    pub fn new(
+        initializer: Box<dyn Initializer>,
+        rak: Arc<RAK>,
+        max_queue_depth: usize,
+    ) -> Arc<Self> {
+        let (tx, rx) = channel::bounded(max_queue_depth);
         let (abort_tx, abort_rx) = channel::bounded(1);
 
         let dispatcher = Arc::new(Dispatcher {
@@ -151,9 +176,21 @@ impl Dispatcher {
     }
 
     /// Queue a new request to be dispatched.
+    ///
+    /// In case the queue is full, `ProtocolError::Overloaded` is returned. It is reported to the
+    /// host as `rhp/dispatcher` error code 2 so that it can be told apart from other dispatch
+    /// failures.
     pub fn queue_request(&self, ctx: Context, id: u64, body: Body) -> AnyResult<()> {
-        self.queue_tx.try_send((ctx, id, body))?;
-        Ok(())
+        match self.queue_tx.try_send((ctx, id, body)) {
+            Ok(()) => Ok(()),
+            Err(channel::TrySendError::Full(_)) => Err(ProtocolError::Overloaded.into()),
+            Err(error) => Err(anyhow!("{}", error)),
+        }
+    }
+
+    /// Number of requests currently waiting in the dispatcher queue.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_tx.len()
     }
 
     /// Signals to dispatcher that it should abort and waits for the abort to
@@ -223,10 +260,14 @@ impl Dispatcher {
                 }
             };
 
+            // Create a per-request logger so that all records can be correlated.
+            let logger = request_logger(&self.logger, id, &request);
+
             let result = match request {
                 Body::RuntimeRPCCallRequest { request } => {
                     // RPC call.
                     self.dispatch_rpc(
+                        &logger,
                         &mut rpc_demux,
                         &mut rpc_dispatcher,
                         &protocol,
@@ -237,7 +278,14 @@ impl Dispatcher {
                 }
                 Body::RuntimeLocalRPCCallRequest { request } => {
                     // Local RPC call.
-                    self.dispatch_local_rpc(&mut rpc_dispatcher, &protocol, &tokio_rt, ctx, request)
+                    self.dispatch_local_rpc(
+                        &logger,
+                        &mut rpc_dispatcher,
+                        &protocol,
+                        &tokio_rt,
+                        ctx,
+                        request,
+                    )
                 }
                 Body::RuntimeExecuteTxBatchRequest {
                     consensus_block,
@@ -256,6 +304,7 @@ impl Dispatcher {
 
                     // Transaction execution.
                     self.dispatch_txn(
+                        &logger,
                         &mut cache,
                         &mut txn_dispatcher,
                         &protocol,
@@ -285,6 +334,7 @@ impl Dispatcher {
 
                     // Transaction check.
                     self.dispatch_txn(
+                        &logger,
                         &mut cache_check,
                         &mut txn_dispatcher,
                         &protocol,
@@ -302,7 +352,12 @@ impl Dispatcher {
                 }
                 Body::RuntimeKeyManagerPolicyUpdateRequest { signed_policy_raw } => {
                     // KeyManager policy update local RPC call.
-                    self.handle_km_policy_update(&mut rpc_dispatcher, ctx, signed_policy_raw)
+                    self.handle_km_policy_update(
+                        &logger,
+                        &mut rpc_dispatcher,
+                        ctx,
+                        signed_policy_raw,
+                    )
                 }
                 Body::RuntimeQueryRequest {
                     consensus_block,
@@ -319,6 +374,7 @@ impl Dispatcher {
 
                     // Query.
                     self.dispatch_query(
+                        &logger,
                         &mut cache_check,
                         &mut txn_dispatcher,
                         &protocol,
@@ -334,11 +390,11 @@ impl Dispatcher {
                 Body::RuntimeAbortRequest {} => {
                     // We handle the RuntimeAbortRequest here so that we break
                     // the recv loop and re-check abort flag.
-                    info!(self.logger, "Received abort request");
+                    info!(logger, "Received abort request");
                     continue 'dispatch;
                 }
                 _ => {
-                    error!(self.logger, "Unsupported request type");
+                    error!(logger, "Unsupported request type");
                     break 'dispatch;
                 }
             };
@@ -357,6 +413,7 @@ impl Dispatcher {
 
     fn dispatch_query(
         &self,
+        logger: &Logger,
         cache: &mut Cache,
         txn_dispatcher: &mut dyn TxnDispatcher,
         protocol: &Arc<Protocol>,
@@ -368,7 +425,7 @@ impl Dispatcher {
         method: String,
         args: cbor::Value,
     ) -> Result<Body, Error> {
-        debug!(self.logger, "Received query request";
+        debug!(logger, "Received query request";
             "state_root" => ?header.state_root,
             "round" => ?header.round,
         );
@@ -418,6 +475,7 @@ impl Dispatcher {
 
     fn txn_check_batch(
         &self,
+        logger: &Logger,
         _ctx: Arc<Context>,
         cache: &mut Cache,
         txn_dispatcher: &mut dyn TxnDispatcher,
@@ -431,13 +489,14 @@ impl Dispatcher {
             txn_dispatcher.check_batch(txn_ctx, &inputs)
         });
 
-        debug!(self.logger, "Transaction batch check complete");
+        debug!(logger, "Transaction batch check complete");
 
         results.map(|results| Body::RuntimeCheckTxBatchResponse { results })
     }
 
     fn txn_execute_batch(
         &self,
+        logger: &Logger,
         ctx: Arc<Context>,
         cache: &mut Cache,
         txn_dispatcher: &mut dyn TxnDispatcher,
@@ -525,7 +584,7 @@ impl Dispatcher {
             messages_hash: Some(roothash::Message::messages_hash(&results.messages)),
         };
 
-        debug!(self.logger, "Transaction batch execution complete";
+        debug!(logger, "Transaction batch execution complete";
             "previous_hash" => ?header.previous_hash,
             "io_root" => ?header.io_root,
             "state_root" => ?header.state_root,
@@ -557,6 +616,7 @@ impl Dispatcher {
 
     fn dispatch_txn(
         &self,
+        logger: &Logger,
         cache: &mut Cache,
         txn_dispatcher: &mut dyn TxnDispatcher,
         protocol: &Arc<Protocol>,
@@ -571,7 +631,7 @@ impl Dispatcher {
         max_messages: u32,
         check_only: bool,
     ) -> Result<Body, Error> {
-        debug!(self.logger, "Received transaction batch request";
+        debug!(logger, "Received transaction batch request";
             "state_root" => ?block.header.state_root,
             "round" => block.header.round + 1,
             "round_results" => ?round_results,
@@ -613,6 +673,7 @@ impl Dispatcher {
         );
         if check_only {
             self.txn_check_batch(
+                logger,
                 ctx,
                 cache,
                 txn_dispatcher,
@@ -623,6 +684,7 @@ impl Dispatcher {
             )
         } else {
             self.txn_execute_batch(
+                logger,
                 ctx,
                 cache,
                 txn_dispatcher,
@@ -636,6 +698,7 @@ impl Dispatcher {
 
     fn dispatch_rpc(
         &self,
+        logger: &Logger,
         rpc_demux: &mut RpcDemux,
         rpc_dispatcher: &mut RpcDispatcher,
         protocol: &Arc<Protocol>,
@@ -643,14 +706,14 @@ impl Dispatcher {
         ctx: Context,
         request: Vec<u8>,
     ) -> Result<Body, Error> {
-        debug!(self.logger, "Received RPC call request");
+        debug!(logger, "Received RPC call request");
 
         // Process frame.
         let mut buffer = vec![];
         let result = match rpc_demux.process_frame(request, &mut buffer) {
             Ok(result) => result,
             Err(error) => {
-                error!(self.logger, "Error while processing frame"; "err" => %error);
+                error!(logger, "Error while processing frame"; "err" => %error);
                 return Err(Error::new("rhp/dispatcher", 1, &format!("{}", error)));
             }
         };
@@ -667,7 +730,7 @@ impl Dispatcher {
                     // First make sure that the untrusted_plaintext matches
                     // the request's method!
                     if untrusted_plaintext != req.method {
-                        error!(self.logger, "Request methods don't match!";
+                        error!(logger, "Request methods don't match!";
                             "untrusted_plaintext" => ?untrusted_plaintext,
                             "method" => ?req.method
                         );
@@ -698,7 +761,7 @@ impl Dispatcher {
 
                     // Note: MKVS commit is omitted, this MUST be global side-effect free.
 
-                    debug!(self.logger, "RPC call dispatch complete");
+                    debug!(logger, "RPC call dispatch complete");
 
                     let mut buffer = vec![];
                     match rpc_demux.write_message(session_id, response, &mut buffer) {
@@ -707,7 +770,7 @@ impl Dispatcher {
                             Ok(Body::RuntimeRPCCallResponse { response: buffer })
                         }
                         Err(error) => {
-                            error!(self.logger, "Error while writing response"; "err" => %error);
+                            error!(logger, "Error while writing response"; "err" => %error);
                             Err(Error::new("rhp/dispatcher", 1, &format!("{}", error)))
                         }
                     }
@@ -721,13 +784,13 @@ impl Dispatcher {
                             Ok(Body::RuntimeRPCCallResponse { response: buffer })
                         }
                         Err(error) => {
-                            error!(self.logger, "Error while closing session"; "err" => %error);
+                            error!(logger, "Error while closing session"; "err" => %error);
                             Err(Error::new("rhp/dispatcher", 1, &format!("{}", error)))
                         }
                     }
                 }
                 msg => {
-                    warn!(self.logger, "Ignoring invalid RPC message type"; "msg" => ?msg);
+                    warn!(logger, "Ignoring invalid RPC message type"; "msg" => ?msg);
                     Err(Error::new("rhp/dispatcher", 1, "invalid RPC message type"))
                 }
             }
@@ -739,13 +802,14 @@ impl Dispatcher {
 
     fn dispatch_local_rpc(
         &self,
+        logger: &Logger,
         rpc_dispatcher: &mut RpcDispatcher,
         protocol: &Arc<Protocol>,
         tokio_rt: &tokio::runtime::Runtime,
         ctx: Context,
         request: Vec<u8>,
     ) -> Result<Body, Error> {
-        debug!(self.logger, "Received local RPC call request");
+        debug!(logger, "Received local RPC call request");
 
         let req: RpcRequest = cbor::from_slice(&request)
             .map_err(|_| Error::new("rhp/dispatcher", 1, "malformed request"))?;
@@ -768,7 +832,7 @@ impl Dispatcher {
 
         // Note: MKVS commit is omitted, this MUST be global side-effect free.
 
-        debug!(self.logger, "Local RPC call dispatch complete");
+        debug!(logger, "Local RPC call dispatch complete");
 
         let response = cbor::to_vec(response);
         Ok(Body::RuntimeLocalRPCCallResponse { response })
@@ -776,13 +840,14 @@ impl Dispatcher {
 
     fn handle_km_policy_update(
         &self,
+        logger: &Logger,
         rpc_dispatcher: &mut RpcDispatcher,
         _ctx: Context,
         signed_policy_raw: Vec<u8>,
     ) -> Result<Body, Error> {
-        debug!(self.logger, "Received km policy update request");
+        debug!(logger, "Received km policy update request");
         rpc_dispatcher.handle_km_policy_update(signed_policy_raw);
-        debug!(self.logger, "KM policy update request complete");
+        debug!(logger, "KM policy update request complete");
 
         Ok(Body::RuntimeKeyManagerPolicyUpdateResponse {})
     }
@@ -825,3 +890,86 @@ impl Cache {
         self.root.hash = root_hash;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::fmt;
+
+    use slog::{Drain, Key, OwnedKVList, Record, Serializer, KV};
+
+    use super::*;
+    use crate::protocol::host_error;
+
+    /// Drain that collects the key/value pairs of all logged records.
+    struct CollectingDrain(Arc<Mutex<Vec<(String, String)>>>);
+
+    struct CollectingSerializer<'a>(&'a mut Vec<(String, String)>);
+
+    impl<'a> Serializer for CollectingSerializer<'a> {
+        fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+            self.0.push((key.to_string(), val.to_string()));
+            Ok(())
+        }
+    }
+
+    impl Drain for CollectingDrain {
+        type Ok = ();
+        type Err = slog::Never;
+
+        fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), slog::Never> {
+            let mut kvs = self.0.lock().unwrap();
+            let mut serializer = CollectingSerializer(&mut *kvs);
+            record.kv().serialize(record, &mut serializer).unwrap();
+            values.serialize(record, &mut serializer).unwrap();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_request_logger() {
+        let kvs = Arc::new(Mutex::new(vec![]));
+        let root = Logger::root(CollectingDrain(kvs.clone()), o!());
+
+        let body = Body::RuntimeLocalRPCCallRequest { request: vec![] };
+        let logger = request_logger(&root, 42, &body);
+        info!(logger, "Test record"; "round" => 1);
+
+        let kvs = kvs.lock().unwrap();
+        assert!(kvs.contains(&("request_id".to_owned(), "42".to_owned())));
+        assert!(kvs.contains(&("method".to_owned(), "RuntimeLocalRPCCallRequest".to_owned())));
+        assert!(kvs.contains(&("round".to_owned(), "1".to_owned())));
+    }
+
+    #[test]
+    fn test_queue_backpressure() {
+        // The dispatch thread waits for the dispatcher to be started, so nothing is taken off
+        // the queue during the test.
+        let initializer = |_: &Arc<Protocol>,
+                           _: &Arc<RAK>,
+                           _: &mut RpcDemux,
+                           _: &mut RpcDispatcher|
+         -> Option<Box<dyn TxnDispatcher>> { None };
+        let dispatcher = Dispatcher::new(Box::new(initializer), Arc::new(RAK::new()), 2);
+
+        for id in 0..2 {
+            dispatcher
+                .queue_request(Context::background(), id, Body::RuntimePingRequest {})
+                .expect("queuing below the limit should succeed");
+        }
+        assert_eq!(dispatcher.queue_depth(), 2);
+
+        // Queuing past the limit should be rejected.
+        let err = dispatcher
+            .queue_request(Context::background(), 2, Body::RuntimePingRequest {})
+            .expect_err("queuing past the limit should fail");
+        assert!(matches!(
+            err.downcast_ref::<ProtocolError>(),
+            Some(ProtocolError::Overloaded)
+        ));
+        assert_eq!(
+            host_error(&err),
+            Error::new("rhp/dispatcher", 2, "dispatcher queue is full")
+        );
+        assert_eq!(dispatcher.queue_depth(), 2);
+    }
+}
@@ -67,20 +67,53 @@ impl Demux {
         self.stale_session_timeout = stale_session_timeout;
     }
 
-    fn purge_stale_sessions(&mut self) {
+    /// Number of currently active sessions.
+    pub fn active_sessions(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Identifiers of all currently active sessions.
+    pub fn session_ids(&self) -> Vec<SessionID> {
+        self.sessions.keys().cloned().collect()
+    }
+
+    /// Closes all sessions that have not processed a frame for at least `idle_timeout` seconds
+    /// as of `now` and returns the number of closed sessions.
+    ///
+    /// Note that an `idle_timeout` of 0 closes all sessions.
+    pub fn prune_expired(&mut self, now: SystemTime, idle_timeout: u64) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|_, val| {
+            now.duration_since(val.last_process_frame_time)
+                .unwrap_or_default()
+                .as_secs()
+                < idle_timeout
+        });
+        before - self.sessions.len()
+    }
+
+    /// Closes sessions that have not processed a frame for longer than the configured stale
+    /// session timeout and returns the number of closed sessions.
+    ///
+    /// The check is performed at most once every STALE_SESSIONS_CHECK_TIMEOUT_SECS seconds,
+    /// calls in between do nothing.
+    fn prune_stale_sessions(&mut self) -> usize {
         let now = insecure_posix_system_time();
-        let stale_session_timeout = self.stale_session_timeout;
+        if now
+            .duration_since(self.last_stale_sessions_purge)
+            .unwrap_or_default()
+            .as_secs()
+            < STALE_SESSIONS_CHECK_TIMEOUT_SECS
+        {
+            return 0;
+        }
+        self.last_stale_sessions_purge = now;
 
         // If 0, sessions should never be considered stale.
-        if stale_session_timeout != 0 {
-            self.sessions.retain(|_, val| {
-                now.duration_since(val.last_process_frame_time)
-                    .unwrap()
-                    .as_secs()
-                    < stale_session_timeout
-            });
+        if self.stale_session_timeout == 0 {
+            return 0;
         }
-        self.last_stale_sessions_purge = now;
+        self.prune_expired(now, self.stale_session_timeout)
     }
 
     /// Process an incoming frame.
@@ -120,17 +153,7 @@ impl Demux {
         } else {
             // Session does not yet exist, first check if any stale sessions
             // should be closed.
-            // Don't check if less than STALE_SESSIONS_CHECK_TIMEOUT_SECS seconds
-            // since last check.
-            let now = insecure_posix_system_time();
-            if now
-                .duration_since(self.last_stale_sessions_purge)
-                .unwrap()
-                .as_secs()
-                >= STALE_SESSIONS_CHECK_TIMEOUT_SECS
-            {
-                self.purge_stale_sessions()
-            }
+            self.prune_stale_sessions();
 
             // Create a new session.
             if self.sessions.len() < self.max_concurrent_sessions {
@@ -190,3 +213,76 @@ impl Demux {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Opens a new session by sending the first handshake frame to the demux.
+    fn open_session(demux: &mut Demux) -> Result<SessionID> {
+        let id = SessionID::random();
+        let mut initiator = Builder::new().build_initiator();
+        let mut payload = vec![];
+        initiator.process_data(vec![], &mut payload)?;
+
+        let frame = cbor::to_vec(Frame {
+            session: id,
+            untrusted_plaintext: "".to_string(),
+            payload,
+        });
+        demux.process_frame(frame, vec![])?;
+
+        Ok(id)
+    }
+
+    #[test]
+    fn test_max_concurrent_sessions() {
+        let mut demux = Demux::new(Arc::new(RAK::new()));
+        demux.set_max_concurrent_sessions(2);
+        assert_eq!(demux.active_sessions(), 0);
+
+        let a = open_session(&mut demux).expect("first session should be accepted");
+        let b = open_session(&mut demux).expect("second session should be accepted");
+        assert_eq!(demux.active_sessions(), 2);
+
+        let mut ids = demux.session_ids();
+        ids.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        // Sessions past the limit should be rejected.
+        let err = open_session(&mut demux).expect_err("third session should be rejected");
+        assert!(matches!(
+            err.downcast_ref::<DemuxError>(),
+            Some(DemuxError::MaxConcurrentSessions)
+        ));
+        assert_eq!(demux.active_sessions(), 2);
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let mut demux = Demux::new(Arc::new(RAK::new()));
+        let idle = open_session(&mut demux).unwrap();
+        let active = open_session(&mut demux).unwrap();
+
+        // Mark one of the sessions as recently active.
+        let now = insecure_posix_system_time() + Duration::from_secs(120);
+        demux
+            .sessions
+            .get_mut(&active)
+            .unwrap()
+            .last_process_frame_time = now;
+
+        // Only the idle session should be pruned.
+        assert_eq!(demux.prune_expired(now, 60), 1);
+        assert!(!demux.session_ids().contains(&idle));
+        assert_eq!(demux.session_ids(), vec![active]);
+
+        // A zero timeout should close all sessions.
+        assert_eq!(demux.prune_expired(now, 0), 1);
+        assert_eq!(demux.active_sessions(), 0);
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
         logger::{get_logger, init_logger},
         version::Version,
     },
-    dispatcher::{Dispatcher, Initializer},
+    dispatcher::{Dispatcher, Initializer, BACKLOG_SIZE},
     protocol::{Protocol, Stream},
     rak::RAK,
 };
@@ -28,7 +28,7 @@ pub fn start_runtime(initializer: Box<dyn Initializer>, version: Version) {
     let rak = Arc::new(RAK::new());
 
     // Initialize the dispatcher.
-    let dispatcher = Dispatcher::new(initializer, rak.clone());
+    let dispatcher = Dispatcher::new(initializer, rak.clone(), BACKLOG_SIZE);
 
     info!(logger, "Establishing connection with the worker host");
 
@@ -12,7 +12,7 @@ use anyhow::{anyhow, Result};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use crossbeam::channel;
 use io_context::Context;
-use slog::{error, info, warn, Logger};
+use slog::{error, info, o, warn, Logger};
 use thiserror::Error;
 
 use crate::{
@@ -48,6 +48,30 @@ pub enum ProtocolError {
     HostInfoNotConfigured,
     #[error("incompatible consensus backend")]
     IncompatibleConsensusBackend,
+    #[error("dispatcher queue is full")]
+    Overloaded,
+}
+
+impl ProtocolError {
+    /// Error code used when reporting the error to the host.
+    ///
+    /// Only a full dispatcher queue has a dedicated code, all other errors use the generic
+    /// dispatcher error code.
+    fn code(&self) -> u32 {
+        match self {
+            ProtocolError::Overloaded => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Convert an error encountered while handling a host request into an error reported to the host.
+pub(crate) fn host_error(error: &anyhow::Error) -> Error {
+    let code = error
+        .downcast_ref::<ProtocolError>()
+        .map(ProtocolError::code)
+        .unwrap_or(1);
+    Error::new("rhp/dispatcher", code, &format!("{}", error))
 }
 
 /// Information about the host environment.
@@ -238,9 +262,7 @@ impl Protocol {
                         // is no need to do anything more.
                         return Ok(());
                     }
-                    Err(error) => {
-                        Body::Error(Error::new("rhp/dispatcher", 1, &format!("{}", error)))
-                    }
+                    Err(error) => Body::Error(host_error(&error)),
                 };
 
                 // Send response back.
@@ -280,6 +302,10 @@ impl Protocol {
         id: u64,
         request: Body,
     ) -> Result<Option<Body>> {
+        // Tag all records with the request identifier so that they can be correlated with the
+        // ones emitted by the dispatcher while handling the same request.
+        let logger = self.logger.new(o!("request_id" => id));
+
         match request {
             Body::RuntimeInfoRequest {
                 runtime_id,
@@ -288,7 +314,7 @@ impl Protocol {
                 consensus_chain_context,
                 local_config,
             } => {
-                info!(self.logger, "Received host environment information";
+                info!(logger, "Received host environment information";
                     "runtime_id" => ?runtime_id,
                     "consensus_backend" => &consensus_backend,
                     "consensus_protocol_version" => ?consensus_protocol_version,
@@ -324,29 +350,26 @@ impl Protocol {
             }
             Body::RuntimePingRequest {} => Ok(Some(Body::Empty {})),
             Body::RuntimeShutdownRequest {} => {
-                info!(self.logger, "Received worker shutdown request");
+                info!(logger, "Received worker shutdown request");
                 Err(ProtocolError::MethodNotSupported.into())
             }
             req @ Body::RuntimeAbortRequest {} => {
-                info!(self.logger, "Received worker abort request");
+                info!(logger, "Received worker abort request");
                 self.can_handle_runtime_requests()?;
                 self.dispatcher.abort_and_wait(ctx, id, req)?;
-                info!(self.logger, "Handled worker abort request");
+                info!(logger, "Handled worker abort request");
                 Ok(Some(Body::RuntimeAbortResponse {}))
             }
             #[cfg(target_env = "sgx")]
             Body::RuntimeCapabilityTEERakInitRequest { target_info } => {
-                info!(self.logger, "Initializing the runtime attestation key");
+                info!(logger, "Initializing the runtime attestation key");
                 self.rak.init_rak(target_info)?;
                 Ok(Some(Body::RuntimeCapabilityTEERakInitResponse {}))
             }
             #[cfg(target_env = "sgx")]
             Body::RuntimeCapabilityTEERakReportRequest {} => {
                 // Initialize the RAK report (for attestation).
-                info!(
-                    self.logger,
-                    "Initializing the runtime attestation key report"
-                );
+                info!(logger, "Initializing the runtime attestation key report");
                 let (rak_pub, report, nonce) = self.rak.init_report();
 
                 let report: &[u8] = report.as_ref();
@@ -361,7 +384,7 @@ impl Protocol {
             #[cfg(target_env = "sgx")]
             Body::RuntimeCapabilityTEERakAvrRequest { avr } => {
                 info!(
-                    self.logger,
+                    logger,
                     "Configuring AVR for the runtime attestation key binding"
                 );
                 self.rak.set_avr(avr)?;
@@ -388,7 +411,7 @@ impl Protocol {
                 Ok(None)
             }
             req @ Body::RuntimeKeyManagerPolicyUpdateRequest { .. } => {
-                info!(self.logger, "Received key manager policy update request");
+                info!(logger, "Received key manager policy update request");
                 self.can_handle_runtime_requests()?;
                 self.dispatcher.queue_request(ctx, id, req)?;
                 Ok(None)
@@ -399,7 +422,7 @@ impl Protocol {
                 Ok(None)
             }
             req => {
-                warn!(self.logger, "Received unsupported request"; "req" => format!("{:?}", req));
+                warn!(logger, "Received unsupported request"; "req" => format!("{:?}", req));
                 Err(ProtocolError::MethodNotSupported.into())
             }
         }
@@ -221,7 +221,7 @@ pub enum Body {
 }
 
 /// A serializable error.
-#[derive(Clone, Debug, Default, Error, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Error, cbor::Encode, cbor::Decode)]
 #[error("module: {module} code: {code} message: {message}")]
 pub struct Error {
     #[cbor(optional)]
@@ -248,6 +248,26 @@ impl Error {
     }
 }
 
+/// Module name used when the error module is unknown.
+const UNKNOWN_MODULE: &str = "unknown";
+
+impl From<anyhow::Error> for Error {
+    /// Converts an arbitrary error into a protocol error.
+    ///
+    /// If the error wraps a protocol error, its module and code are preserved. Otherwise the
+    /// error is reported as an unknown error with the formatted error (including its chain of
+    /// causes) as the message, which matches how go/common/errors encodes uncoded errors.
+    ///
+    /// This is meant for glue code. Errors produced while dispatching host requests are still
+    /// reported under the `rhp/dispatcher` module.
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<Error>() {
+            Ok(err) => err,
+            Err(err) => Self::new(UNKNOWN_MODULE, 1, &format!("{:#}", err)),
+        }
+    }
+}
+
 /// Result of a CheckTx operation.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct CheckTxResult {
@@ -330,6 +350,29 @@ pub struct Message {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_error_conversion() {
+        let err = Error::new("test", 42, "some error");
+        let anyhow_err: anyhow::Error = err.clone().into();
+        assert_eq!(
+            format!("{}", anyhow_err),
+            "module: test code: 42 message: some error"
+        );
+        let back: Error = anyhow_err.into();
+        assert_eq!(back, err);
+
+        let err: Error = anyhow::anyhow!("something failed").into();
+        assert_eq!(err, Error::new("unknown", 1, "something failed"));
+
+        let err: Error = anyhow::anyhow!("root cause")
+            .context("something failed")
+            .into();
+        assert_eq!(
+            err,
+            Error::new("unknown", 1, "something failed: root cause")
+        );
+    }
+
     #[test]
     fn test_consistent_check_tx_weight() {
         let tcs = vec![